@@ -0,0 +1,72 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IRQ handling, called from the `irq_current` entry of the EL1 exception vector table.
+//!
+//! This drains the GIC rather than leaving `main` to busy-poll a peripheral's own status
+//! register, mirroring the peripheral-interrupt handling flow in the rust-raspberrypi-OS
+//! tutorials.
+
+use crate::gicv3::{GicV3, IntId};
+use crate::sgi_channel::CHANNEL;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use log::info;
+
+/// Offset of the PL031's `ICR` (Interrupt Clear Register) from its base address.
+const PL031_ICR_OFFSET: usize = 0x01c;
+
+/// Set by the IRQ handler once it has seen the interrupt `main` asked to be woken up by, so
+/// `main` can wait on this instead of busy-polling the peripheral's own match register.
+pub static WOKEN: AtomicBool = AtomicBool::new(false);
+
+/// Base address of the PL031 whose match interrupt should wake `main`, or 0 if none has been
+/// set yet.
+static WAKEUP_RTC_BASE: AtomicUsize = AtomicUsize::new(0);
+
+/// The INTID that should be treated as the RTC wakeup interrupt.
+static WAKEUP_INTID: AtomicU32 = AtomicU32::new(0);
+
+/// Arms the IRQ handler to clear the given PL031's match condition and set [`WOKEN`] the next
+/// time `intid` fires.
+///
+/// Must be called before interrupts are unmasked.
+pub fn set_wakeup_source(rtc_base: *mut u32, intid: IntId) {
+    WAKEUP_RTC_BASE.store(rtc_base as usize, Ordering::Release);
+    WAKEUP_INTID.store(intid.into(), Ordering::Release);
+}
+
+/// Called from the EL1 IRQ vector entry for every interrupt taken while the CPU is at EL1.
+///
+/// Drains all pending Group 1 interrupts rather than handling just one, since a level-triggered
+/// interrupt (like the PL031's) can still be pending when this returns.
+#[no_mangle]
+extern "C" fn irq_current() {
+    while let Some(intid) = GicV3::acknowledge_interrupt() {
+        if u32::from(intid) == WAKEUP_INTID.load(Ordering::Acquire) {
+            let rtc_base = WAKEUP_RTC_BASE.load(Ordering::Acquire) as *mut u32;
+            if !rtc_base.is_null() {
+                // Safe because `rtc_base` is the base of a PL031's registers, given to us by
+                // `main` via `set_wakeup_source` before interrupts were unmasked, and nothing
+                // else writes to it while interrupts are enabled.
+                unsafe {
+                    rtc_base.byte_add(PL031_ICR_OFFSET).write_volatile(1);
+                }
+            }
+            WOKEN.store(true, Ordering::Release);
+        } else if intid == CHANNEL.doorbell() {
+            CHANNEL.drain(|msg| info!("Received message {msg} over SGI channel"));
+        }
+        GicV3::end_interrupt(intid);
+    }
+}
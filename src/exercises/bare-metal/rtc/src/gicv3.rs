@@ -13,9 +13,260 @@
 // limitations under the License.
 
 use bitflags::bitflags;
+use core::hint::spin_loop;
 use core::ptr::{addr_of, addr_of_mut};
 use log::info;
 
+/// `GICR_TYPER.PLPIS`: whether this redistributor supports LPIs.
+const GICR_TYPER_PLPIS: u64 = 1 << 0;
+/// `GICD_TYPER.IDbits`: number of interrupt ID bits supported distributor-wide, minus one. This
+/// bounds the number of LPI INTIDs software may configure via `GICR_PROPBASER.IDbits`.
+const GICD_TYPER_IDBITS_SHIFT: u32 = 19;
+const GICD_TYPER_IDBITS_MASK: u32 = 0x1f << GICD_TYPER_IDBITS_SHIFT;
+
+/// `GICR_CTLR.EnableLPIs`.
+const GICR_CTLR_ENABLE_LPIS: u32 = 1 << 0;
+
+/// `GICR_SYNCR.Busy`.
+const GICR_SYNCR_BUSY: u32 = 1 << 0;
+
+/// Required alignment of the LPI configuration (property) table.
+const LPI_PROPBASE_ALIGNMENT: u64 = 4 * 1024;
+/// Required alignment of the LPI pending table.
+const LPI_PENDBASE_ALIGNMENT: u64 = 64 * 1024;
+/// The first INTID in the LPI range; INTIDs below this are SGIs, PPIs, SPIs or special INTIDs.
+const LPI_INTID_BASE: u32 = 8192;
+
+/// Inner Shareable, as encoded in `GICR_PROPBASER.Shareability` / `GICR_PENDBASER.Shareability`.
+const INNER_SHAREABLE: u64 = 0b01 << 10;
+/// Normal Inner Write-Back Cacheable, Read-Allocate, Write-Allocate, as encoded in
+/// `GICR_PROPBASER.InnerCache` / `GICR_PENDBASER.InnerCache`.
+const CACHE_NORMAL_INNER_WB_RA_WA: u64 = 0b111 << 7;
+
+/// `GICD_IROUTER<n>.Interrupt_Routing_Mode`: route to any participating PE (ignoring all the
+/// affinity fields), rather than to the single PE identified by all four affinity fields.
+const IROUTER_IRM: u64 = 1 << 31;
+
+/// The first SPI INTID, and the last SPI INTID (inclusive), covered by `GICD.irouter`.
+const SPI_MIN: u32 = 32;
+const SPI_MAX: u32 = 1019;
+/// The first and last (inclusive) extended SPI INTID, covered by `GICD.irouter_e`.
+const EXTENDED_SPI_MIN: u32 = 4096;
+const EXTENDED_SPI_MAX: u32 = 5119;
+
+/// `GICD_TYPER.ESPI`: whether the distributor implements the extended SPI range.
+const GICD_TYPER_ESPI: u32 = 1 << 8;
+/// `GICR_TYPER.PPInum`: which extended PPIs are implemented by this redistributor, 0 if none.
+const GICR_TYPER_PPINUM_SHIFT: u32 = 27;
+const GICR_TYPER_PPINUM_MASK: u64 = 0x1f << GICR_TYPER_PPINUM_SHIFT;
+
+/// The trigger configuration of an interrupt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Trigger {
+    /// The interrupt is asserted when its source transitions from inactive to active.
+    Edge,
+    /// The interrupt is asserted whenever its source is active.
+    Level,
+}
+
+/// An error returned when an operation targets an extended SPI or extended PPI that the
+/// hardware doesn't implement.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExtendedRangeError {
+    /// `GICD_TYPER.ESPI` is not set; this distributor doesn't implement the extended SPI range.
+    ExtendedSpiNotSupported,
+    /// `GICR_TYPER.PPInum` is zero; this redistributor doesn't implement any extended PPIs.
+    ExtendedPpiNotSupported,
+}
+
+/// Which register block and local index within it an INTID is handled by.
+enum IntIdLocation {
+    /// An SGI or PPI (0-31), handled by the `SGI` redistributor frame at the given index.
+    Private(u32),
+    /// An extended PPI, handled by the `SGI` redistributor frame's `*_e` registers.
+    ExtendedPrivate(u32),
+    /// An SPI, handled by the distributor's base-range registers.
+    Shared(u32),
+    /// An extended SPI, handled by the distributor's `*_e` registers.
+    ExtendedShared(u32),
+}
+
+impl IntIdLocation {
+    /// Classifies a raw INTID, returning its register block and index within that block.
+    ///
+    /// Returns `None` for LPIs, the special INTIDs and other values outside the SGI, PPI and SPI
+    /// ranges.
+    fn of(intid: u32) -> Option<Self> {
+        match intid {
+            0..=31 => Some(Self::Private(intid)),
+            SPI_MIN..=SPI_MAX => Some(Self::Shared(intid - SPI_MIN)),
+            EXTENDED_PPI_BASE..=EXTENDED_PPI_END => {
+                Some(Self::ExtendedPrivate(intid - EXTENDED_PPI_BASE))
+            }
+            EXTENDED_SPI_MIN..=EXTENDED_SPI_MAX => {
+                Some(Self::ExtendedShared(intid - EXTENDED_SPI_MIN))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The first and last (inclusive) extended PPI INTID, covered by `SGI.*_e` registers.
+const EXTENDED_PPI_BASE: u32 = 1056;
+const EXTENDED_PPI_END: u32 = 1119;
+
+/// The target of an interrupt, as programmed into a `GICD_IROUTER` register.
+///
+/// SGIs and PPIs aren't affinity-routed this way; they always target the redistributor they're
+/// configured on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Affinity {
+    pub aff3: u8,
+    pub aff2: u8,
+    pub aff1: u8,
+    pub aff0: u8,
+    /// Sets `Interrupt_Routing_Mode`, routing to any participating PE (ignoring all four affinity
+    /// fields) instead of to the single PE identified by all four affinity fields.
+    pub one_of_n: bool,
+}
+
+impl Affinity {
+    /// Reads the affinity of the calling PE from `MPIDR_EL1`.
+    pub fn current() -> Self {
+        let mpidr = unsafe { read_sysreg!(mpidr_el1) };
+        Self {
+            aff0: mpidr as u8,
+            aff1: (mpidr >> 8) as u8,
+            aff2: (mpidr >> 16) as u8,
+            aff3: (mpidr >> 32) as u8,
+            one_of_n: false,
+        }
+    }
+
+    /// Packs the affinity fields into the low 40 bits of a `GICD_IROUTER` value, setting
+    /// `Interrupt_Routing_Mode` if `one_of_n` is set.
+    fn routing_value(self) -> u64 {
+        let affinity = u64::from(self.aff0)
+            | u64::from(self.aff1) << 8
+            | u64::from(self.aff2) << 16
+            | u64::from(self.aff3) << 32;
+        if self.one_of_n {
+            affinity | IROUTER_IRM
+        } else {
+            affinity
+        }
+    }
+
+    /// Returns whether `self` and `other` name the same PE, ignoring `one_of_n` (which is
+    /// meaningless when identifying a single target rather than routing an interrupt).
+    fn same_target(self, other: Self) -> bool {
+        self.aff0 == other.aff0
+            && self.aff1 == other.aff1
+            && self.aff2 == other.aff2
+            && self.aff3 == other.aff3
+    }
+}
+
+/// An error returned by [`GicV3::set_routing`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoutingError {
+    /// The given `IntId` isn't an SPI or extended SPI; SGIs and PPIs are routed to a
+    /// redistributor directly rather than via `GICD_IROUTER`.
+    NotRoutable,
+}
+
+/// Errors that can occur while configuring LPI support on a redistributor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LpiError {
+    /// `GICR_TYPER.PLPIS` is not set, so this redistributor doesn't support LPIs.
+    NotSupported,
+    /// The property table isn't aligned to `LPI_PROPBASE_ALIGNMENT` (4KiB).
+    PropertyTableMisaligned,
+    /// The pending table isn't aligned to `LPI_PENDBASE_ALIGNMENT` (64KiB).
+    PendingTableMisaligned,
+    /// The property table is too small to hold an entry for every LPI implied by
+    /// `GICD_TYPER.IDbits`.
+    PropertyTableTooSmall,
+    /// The pending table is too small to hold a pending bit for every supported INTID.
+    PendingTableTooSmall,
+}
+
+/// An interrupt ID, distinguishing SGIs, PPIs, SPIs and their extended ranges rather than
+/// passing around a raw `u32` that could be any of them.
+///
+/// See the GICv3 and GICv4 Architecture Specification for the INTID ranges this encodes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IntId(u32);
+
+impl IntId {
+    const SGI_MAX: u32 = 15;
+    const PPI_MAX: u32 = 15;
+    const SPI_MAX: u32 = 987;
+    const SPECIAL_START: u32 = 1020;
+    const SPECIAL_END: u32 = 1023;
+    const EXTENDED_PPI_MAX: u32 = 63;
+    const EXTENDED_PPI_BASE: u32 = 1056;
+    const EXTENDED_SPI_MAX: u32 = 1023;
+    const EXTENDED_SPI_BASE: u32 = 4096;
+
+    /// Returns the `IntId` for the given Software Generated Interrupt.
+    pub const fn sgi(sgi: u32) -> Self {
+        assert!(sgi <= Self::SGI_MAX);
+        Self(sgi)
+    }
+
+    /// Returns the `IntId` for the given Private Peripheral Interrupt.
+    pub const fn ppi(ppi: u32) -> Self {
+        assert!(ppi <= Self::PPI_MAX);
+        Self(Self::SGI_MAX + 1 + ppi)
+    }
+
+    /// Returns the `IntId` for the given Shared Peripheral Interrupt.
+    pub const fn spi(spi: u32) -> Self {
+        assert!(spi <= Self::SPI_MAX);
+        Self(Self::SGI_MAX + Self::PPI_MAX + 2 + spi)
+    }
+
+    /// Returns the `IntId` for the given extended-range Private Peripheral Interrupt.
+    pub const fn extended_ppi(ppi: u32) -> Self {
+        assert!(ppi <= Self::EXTENDED_PPI_MAX);
+        Self(Self::EXTENDED_PPI_BASE + ppi)
+    }
+
+    /// Returns the `IntId` for the given extended-range Shared Peripheral Interrupt.
+    pub const fn extended_spi(spi: u32) -> Self {
+        assert!(spi <= Self::EXTENDED_SPI_MAX);
+        Self(Self::EXTENDED_SPI_BASE + spi)
+    }
+
+    /// Wraps a raw INTID read from hardware (e.g. `ICC_IAR1_EL1`), returning `None` if it is one
+    /// of the special INTIDs (1020-1023) that don't correspond to a real interrupt.
+    fn from_raw(raw: u32) -> Option<Self> {
+        if (Self::SPECIAL_START..=Self::SPECIAL_END).contains(&raw) {
+            None
+        } else {
+            Some(Self(raw))
+        }
+    }
+}
+
+impl From<u32> for IntId {
+    /// Wraps a raw INTID value directly, with no classification or range check.
+    ///
+    /// This is an identity wrap, not a constructor for any particular interrupt type — `2` stays
+    /// `2`, it does not become SPI 2. Prefer [`IntId::spi`], [`IntId::ppi`] or [`IntId::sgi`] when
+    /// naming a specific interrupt by its type-relative number.
+    fn from(intid: u32) -> Self {
+        Self(intid)
+    }
+}
+
+impl From<IntId> for u32 {
+    fn from(intid: IntId) -> Self {
+        intid.0
+    }
+}
+
 macro_rules! read_sysreg {
     ($name:ident) => {
         {
@@ -144,11 +395,15 @@ struct GICD {
     /// Non-maskable interrupt registers for extended SPI range.
     inmr_e: [u32; 32],
     _reserved19: [u32; 2400],
-    /// Interrupt routing registers.
-    irouter: [u32; 1975],
-    _reserved20: [u32; 9],
-    /// Interrupt routing registers for extended SPI range.
-    irouter_e: [u32; 2048],
+    /// Interrupt routing registers, one 64-bit `GICD_IROUTER<n>` per SPI (`n` from 32 to 1019).
+    ///
+    /// Accessed as `u64` rather than as a pair of `u32`s so that a single volatile write can't
+    /// tear the affinity value across two bus accesses.
+    irouter: [u64; 988],
+    _reserved20: [u64; 4],
+    /// Interrupt routing registers for the extended SPI range, one 64-bit
+    /// `GICD_IROUTER_E<n>` per extended SPI (`n` from 4096 to 5119).
+    irouter_e: [u64; 1024],
     _reserved21: [u32; 2048],
     /// Implementation defined registers.
     implementation_defined2: [u32; 4084],
@@ -282,6 +537,45 @@ struct SGI {
     _reserved13: [u32; 12],
 }
 
+/// `GICR_TYPER`, decoded enough to enumerate a multicore redistributor region.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct GicrTyper(u64);
+
+impl GicrTyper {
+    /// `VLPIS`: whether this redistributor additionally implements the `VLPI_base` and reserved
+    /// pages, making its frame 256KiB rather than 128KiB.
+    fn vlpis(self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// `Last`: whether this is the final redistributor frame in the contiguous region.
+    fn is_last(self) -> bool {
+        self.0 & (1 << 4) != 0
+    }
+
+    /// `Affinity_Value`: the affinity of the PE this redistributor frame is associated with.
+    fn affinity(self) -> Affinity {
+        let value = self.0 >> 32;
+        Affinity {
+            aff0: value as u8,
+            aff1: (value >> 8) as u8,
+            aff2: (value >> 16) as u8,
+            aff3: (value >> 24) as u8,
+            one_of_n: false,
+        }
+    }
+
+    /// The size in bytes of this redistributor's frames: `RD_base` + `SGI_base`, plus
+    /// `VLPI_base` and a reserved page if `VLPIS` is implemented.
+    fn frame_size(self) -> usize {
+        if self.vlpis() {
+            4 * SGI_OFFSET
+        } else {
+            2 * SGI_OFFSET
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GicV3 {
     gicd: *mut GICD,
@@ -290,11 +584,32 @@ pub struct GicV3 {
 }
 
 impl GicV3 {
-    pub unsafe fn new(gicd: *mut u64, gicr: *mut u64) -> Self {
-        Self {
-            gicd: gicd as _,
-            gicr: gicr as _,
-            sgi: gicr.offset(SGI_OFFSET) as _,
+    /// Walks a multicore redistributor region starting at `gicr_base`, which may contain one
+    /// 128KiB (or 256KiB, if `VLPIS` is implemented) frame per PE, and builds a `GicV3` using
+    /// the frame whose `GICR_TYPER.Affinity_Value` matches the calling PE's `MPIDR_EL1`.
+    ///
+    /// Returns `None` if the `Last` frame is reached without finding a match.
+    ///
+    /// # Safety
+    /// `gicd` must be the base of a valid GICv3 distributor, and `gicr_base` the base of a
+    /// valid, contiguous GICv3 redistributor region containing at least one frame with the
+    /// `Last` bit set, for as long as the returned `GicV3` is used.
+    pub unsafe fn new_for_current_cpu(gicd: *mut u64, gicr_base: *mut u64) -> Option<Self> {
+        let current_affinity = Affinity::current();
+        let mut gicr = gicr_base as *mut GICR;
+        loop {
+            let typer = GicrTyper(addr_of!((*gicr).typer).read_volatile());
+            if typer.affinity().same_target(current_affinity) {
+                return Some(Self {
+                    gicd: gicd as _,
+                    gicr,
+                    sgi: (gicr as *mut u8).add(SGI_OFFSET) as _,
+                });
+            }
+            if typer.is_last() {
+                return None;
+            }
+            gicr = (gicr as *mut u8).add(typer.frame_size()) as *mut GICR;
         }
     }
 
@@ -332,5 +647,378 @@ impl GicV3 {
             write_sysreg!(icc_igrpen1_el1, 0x00000001);
         }
     }
+
+    /// Enables LPI support on this redistributor, programming `GICR_PROPBASER` and
+    /// `GICR_PENDBASER` to point at the given tables and setting `GICR_CTLR.EnableLPIs`.
+    ///
+    /// `property_table` must be 4KiB-aligned and `pending_table` must be 64KiB-aligned and
+    /// zeroed, per the GICv3 architecture requirements. Both must be big enough for the number
+    /// of LPIs implied by `GICD_TYPER.IDbits`.
+    pub fn setup_lpis(
+        &mut self,
+        property_table: &mut [u8],
+        pending_table: &mut [u8],
+    ) -> Result<(), LpiError> {
+        let gicr_typer = unsafe { addr_of!((*self.gicr).typer).read_volatile() };
+        if gicr_typer & GICR_TYPER_PLPIS == 0 {
+            return Err(LpiError::NotSupported);
+        }
+
+        let property_table_address = property_table.as_ptr() as u64;
+        let pending_table_address = pending_table.as_ptr() as u64;
+        if property_table_address % LPI_PROPBASE_ALIGNMENT != 0 {
+            return Err(LpiError::PropertyTableMisaligned);
+        }
+        if pending_table_address % LPI_PENDBASE_ALIGNMENT != 0 {
+            return Err(LpiError::PendingTableMisaligned);
+        }
+
+        let gicd_typer = unsafe { addr_of!((*self.gicd).typer).read_volatile() };
+        let id_bits =
+            u64::from((gicd_typer & GICD_TYPER_IDBITS_MASK) >> GICD_TYPER_IDBITS_SHIFT) + 1;
+        let lpi_count = (1u64 << id_bits).saturating_sub(LPI_INTID_BASE.into());
+        if (property_table.len() as u64) < lpi_count {
+            return Err(LpiError::PropertyTableTooSmall);
+        }
+        if (pending_table.len() as u64) * 8 < 1u64 << id_bits {
+            return Err(LpiError::PendingTableTooSmall);
+        }
+
+        let propbaser =
+            property_table_address | CACHE_NORMAL_INNER_WB_RA_WA | INNER_SHAREABLE | (id_bits - 1);
+        let pendbaser = pending_table_address | CACHE_NORMAL_INNER_WB_RA_WA | INNER_SHAREABLE;
+
+        unsafe {
+            addr_of_mut!((*self.gicr).propbaser).write_volatile(propbaser);
+            addr_of_mut!((*self.gicr).pendbaser).write_volatile(pendbaser);
+
+            let ctlr = addr_of!((*self.gicr).ctlr).read_volatile();
+            addr_of_mut!((*self.gicr).ctlr).write_volatile(ctlr | GICR_CTLR_ENABLE_LPIS);
+        }
+
+        Ok(())
+    }
+
+    /// Sets whether the given LPI is enabled in `property_table`, and invalidates it so the
+    /// redistributor picks up the change.
+    ///
+    /// `intid` must be in the LPI range (`>= 8192`) and `property_table` must be the same table
+    /// previously passed to [`setup_lpis`](Self::setup_lpis).
+    pub fn set_lpi_enabled(&mut self, property_table: &mut [u8], intid: u32, enabled: bool) {
+        let byte = &mut property_table[(intid - LPI_INTID_BASE) as usize];
+        if enabled {
+            *byte |= 1 << 0;
+        } else {
+            *byte &= !(1 << 0);
+        }
+        self.invalidate_lpi(intid);
+    }
+
+    /// Sets the priority of the given LPI in `property_table`, and invalidates it so the
+    /// redistributor picks up the change.
+    ///
+    /// `intid` must be in the LPI range (`>= 8192`) and `property_table` must be the same table
+    /// previously passed to [`setup_lpis`](Self::setup_lpis).
+    pub fn set_lpi_priority(&mut self, property_table: &mut [u8], intid: u32, priority: u8) {
+        let byte = &mut property_table[(intid - LPI_INTID_BASE) as usize];
+        *byte = (*byte & 0x3) | (priority & !0x3);
+        self.invalidate_lpi(intid);
+    }
+
+    /// Invalidates the redistributor's cached config for a single LPI, waiting for the
+    /// redistributor to become idle.
+    fn invalidate_lpi(&mut self, intid: u32) {
+        unsafe {
+            addr_of_mut!((*self.gicr).invlpir).write_volatile(u64::from(intid));
+        }
+        self.wait_for_rd_idle();
+    }
+
+    /// Invalidates the redistributor's entire cached LPI config, waiting for the redistributor
+    /// to become idle. Useful after changing several LPIs at once, as it avoids one `invlpir`
+    /// write (and sync poll) per LPI.
+    pub fn invalidate_all_lpis(&mut self) {
+        unsafe {
+            addr_of_mut!((*self.gicr).invallr).write_volatile(0);
+        }
+        self.wait_for_rd_idle();
+    }
+
+    /// Polls `GICR_SYNCR.Busy` until the redistributor has finished processing an invalidation.
+    fn wait_for_rd_idle(&self) {
+        while unsafe { addr_of!((*self.gicr).syncr).read_volatile() } & GICR_SYNCR_BUSY != 0 {
+            spin_loop();
+        }
+    }
+
+    /// Reads `ICC_IAR1_EL1` to acknowledge the highest priority pending Group 1 interrupt for
+    /// the running PE, returning its [`IntId`].
+    ///
+    /// Returns `None` if there is no pending interrupt to acknowledge, signalled by the special
+    /// INTID 1023 ("no pending interrupt").
+    pub fn acknowledge_interrupt() -> Option<IntId> {
+        let intid = unsafe { read_sysreg!(icc_iar1_el1) } as u32;
+        IntId::from_raw(intid)
+    }
+
+    /// Writes `ICC_EOIR1_EL1` to inform the GIC that the given interrupt has been handled,
+    /// dropping its running priority.
+    ///
+    /// If `ICC_CTLR_EL1.EOImode` is 1 then priority drop and deactivation are split, and this
+    /// must be followed by [`deactivate_interrupt`](Self::deactivate_interrupt).
+    pub fn end_interrupt(intid: IntId) {
+        unsafe { write_sysreg!(icc_eoir1_el1, u32::from(intid).into()) }
+    }
+
+    /// Writes `ICC_DIR_EL1` to deactivate the given interrupt.
+    ///
+    /// Only needed when `ICC_CTLR_EL1.EOImode` is 1, which splits deactivation from priority
+    /// drop (`end_interrupt`).
+    pub fn deactivate_interrupt(intid: IntId) {
+        unsafe { write_sysreg!(icc_dir_el1, u32::from(intid).into()) }
+    }
+
+    /// Writes `ICC_SGI1R_EL1` to send a Software Generated Interrupt to the PEs named by
+    /// `aff3`.`aff2`.`aff1` and `target_list`.
+    ///
+    /// `target_list` is a bitmap of `Aff0` values (within the named `aff1` cluster) to send the
+    /// SGI to; if `irm` is set then `target_list` is ignored and the SGI is sent to every PE in
+    /// the system other than the one sending it, regardless of affinity.
+    pub fn send_sgi(sgi_intid: u32, irm: bool, aff3: u8, aff2: u8, aff1: u8, target_list: u16) {
+        let mut value = u64::from(target_list)
+            | u64::from(aff1) << 16
+            | u64::from(sgi_intid) << 24
+            | u64::from(aff2) << 32
+            | u64::from(aff3) << 48;
+        if irm {
+            value |= 1 << 40;
+        }
+        unsafe { write_sysreg!(icc_sgi1r_el1, value) }
+    }
+
+    /// Routes the given SPI (or extended SPI) to `target`, by programming its `GICD_IROUTER`
+    /// (or `GICD_IROUTER_E`) register.
+    ///
+    /// Returns [`RoutingError::NotRoutable`] if `intid` is an SGI or PPI, neither of which are
+    /// routed via the distributor.
+    pub fn set_routing(&mut self, intid: IntId, target: Affinity) -> Result<(), RoutingError> {
+        let raw = u32::from(intid);
+        let value = target.routing_value();
+        unsafe {
+            if (SPI_MIN..=SPI_MAX).contains(&raw) {
+                addr_of_mut!((*self.gicd).irouter[(raw - SPI_MIN) as usize]).write_volatile(value);
+            } else if (EXTENDED_SPI_MIN..=EXTENDED_SPI_MAX).contains(&raw) {
+                addr_of_mut!((*self.gicd).irouter_e[(raw - EXTENDED_SPI_MIN) as usize])
+                    .write_volatile(value);
+            } else {
+                return Err(RoutingError::NotRoutable);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `GICD_TYPER.ESPI`, returning an error if the extended SPI range isn't implemented.
+    fn check_extended_spi_supported(&self) -> Result<(), ExtendedRangeError> {
+        let typer = unsafe { addr_of!((*self.gicd).typer).read_volatile() };
+        if typer & GICD_TYPER_ESPI == 0 {
+            Err(ExtendedRangeError::ExtendedSpiNotSupported)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks `GICR_TYPER.PPInum`, returning an error if this redistributor doesn't implement
+    /// any extended PPIs.
+    fn check_extended_ppi_supported(&self) -> Result<(), ExtendedRangeError> {
+        let typer = unsafe { addr_of!((*self.gicr).typer).read_volatile() };
+        if typer & GICR_TYPER_PPINUM_MASK == 0 {
+            Err(ExtendedRangeError::ExtendedPpiNotSupported)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Enables (or disables) every interrupt in the base SGI, PPI and SPI ranges, plus the
+    /// extended SPI and PPI ranges if this GIC implements them.
+    pub fn enable_all_interrupts(&mut self, enable: bool) {
+        // Writing 1s to `ISENABLER` enables and to `ICENABLER` disables; 0s have no effect.
+        let value = 0xffff_ffff;
+        unsafe {
+            for i in 0..32 {
+                if enable {
+                    addr_of_mut!((*self.gicd).isenabler[i]).write_volatile(value);
+                } else {
+                    addr_of_mut!((*self.gicd).icenabler[i]).write_volatile(value);
+                }
+            }
+            if enable {
+                addr_of_mut!((*self.sgi).isenabler0).write_volatile(value);
+            } else {
+                addr_of_mut!((*self.sgi).icenabler0).write_volatile(value);
+            }
+
+            if self.check_extended_spi_supported().is_ok() {
+                for i in 0..32 {
+                    if enable {
+                        addr_of_mut!((*self.gicd).isenabler_e[i]).write_volatile(value);
+                    } else {
+                        addr_of_mut!((*self.gicd).icenabler_e[i]).write_volatile(value);
+                    }
+                }
+            }
+            if self.check_extended_ppi_supported().is_ok() {
+                for i in 0..2 {
+                    if enable {
+                        addr_of_mut!((*self.sgi).isenabler_e[i]).write_volatile(value);
+                    } else {
+                        addr_of_mut!((*self.sgi).icenabler_e[i]).write_volatile(value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sets the priority of the given interrupt, in whichever of the base or extended SGI/PPI/
+    /// SPI register blocks it belongs to.
+    pub fn set_interrupt_priority(
+        &mut self,
+        intid: IntId,
+        priority: u8,
+    ) -> Result<(), ExtendedRangeError> {
+        let raw = u32::from(intid);
+        match IntIdLocation::of(raw) {
+            Some(IntIdLocation::Private(n)) => unsafe {
+                addr_of_mut!((*self.sgi).ipriorityr[n as usize]).write_volatile(priority);
+            },
+            Some(IntIdLocation::Shared(_)) => unsafe {
+                // `GICD_IPRIORITYR` is byte-indexed by the *absolute* INTID (the array starts
+                // at INTID 0), unlike the extended range below, which is relative to
+                // `EXTENDED_SPI_MIN`.
+                addr_of_mut!((*self.gicd).ipriorityr[raw as usize]).write_volatile(priority);
+            },
+            Some(IntIdLocation::ExtendedPrivate(n)) => {
+                self.check_extended_ppi_supported()?;
+                unsafe {
+                    addr_of_mut!((*self.sgi).ipriorityr_e[n as usize]).write_volatile(priority);
+                }
+            }
+            Some(IntIdLocation::ExtendedShared(n)) => {
+                self.check_extended_spi_supported()?;
+                unsafe {
+                    addr_of_mut!((*self.gicd).ipriorityr_e[n as usize]).write_volatile(priority);
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Sets the trigger configuration of the given interrupt, in whichever of the base or
+    /// extended PPI/SPI register blocks it belongs to.
+    ///
+    /// SGIs are always edge-triggered and have no configurable trigger, so this has no effect
+    /// for INTIDs 0-15.
+    pub fn set_trigger(
+        &mut self,
+        intid: IntId,
+        trigger: Trigger,
+    ) -> Result<(), ExtendedRangeError> {
+        let raw = u32::from(intid);
+        match IntIdLocation::of(raw) {
+            Some(IntIdLocation::Private(n)) if n < 16 => {}
+            Some(IntIdLocation::Private(n)) => unsafe {
+                Self::write_icfgr_bit(addr_of_mut!((*self.sgi).icfgr1), n - 16, trigger);
+            },
+            Some(IntIdLocation::Shared(_)) => unsafe {
+                // `GICD_ICFGR` is indexed by the absolute INTID (`icfgr[intid / 16]`, bit
+                // `intid % 16`), unlike the extended range below, which is relative to
+                // `EXTENDED_SPI_MIN`.
+                Self::write_icfgr_bit(
+                    addr_of_mut!((*self.gicd).icfgr[(raw / 16) as usize]),
+                    raw % 16,
+                    trigger,
+                );
+            },
+            Some(IntIdLocation::ExtendedPrivate(n)) => {
+                self.check_extended_ppi_supported()?;
+                unsafe {
+                    Self::write_icfgr_bit(
+                        addr_of_mut!((*self.sgi).icfgr_e[(n / 16) as usize]),
+                        n % 16,
+                        trigger,
+                    );
+                }
+            }
+            Some(IntIdLocation::ExtendedShared(n)) => {
+                self.check_extended_spi_supported()?;
+                unsafe {
+                    Self::write_icfgr_bit(
+                        addr_of_mut!((*self.gicd).icfgr_e[(n / 16) as usize]),
+                        n % 16,
+                        trigger,
+                    );
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Sets or clears the `Int_config` bit for interrupt `index_in_register` (0-15) within a
+    /// 32-bit `*_ICFGR<n>` register.
+    unsafe fn write_icfgr_bit(register: *mut u32, index_in_register: u32, trigger: Trigger) {
+        let bit = 1 << (2 * index_in_register + 1);
+        let mut value = register.read_volatile();
+        match trigger {
+            Trigger::Edge => value |= bit,
+            Trigger::Level => value &= !bit,
+        }
+        register.write_volatile(value);
+    }
+
+    /// Reads `GICD_ISPENDR<i>`, the pending state of SPIs `32*i` to `32*i + 31`.
+    pub fn gicd_pending(&self, i: usize) -> u32 {
+        unsafe { addr_of!((*self.gicd).ispendr[i]).read_volatile() }
+    }
+
+    /// Reads `GICD_ISPENDR_E<i>`, the pending state of extended SPIs `32*i` to `32*i + 31`.
+    pub fn gicd_pending_e(&self, i: usize) -> Result<u32, ExtendedRangeError> {
+        self.check_extended_spi_supported()?;
+        Ok(unsafe { addr_of!((*self.gicd).ispendr_e[i]).read_volatile() })
+    }
+
+    /// Reads the SGI frame's `GICR_ISPENDR0`, the pending state of SGIs and PPIs 0-31.
+    pub fn gicr_pending(&self) -> u32 {
+        unsafe { addr_of!((*self.sgi).ispendr0).read_volatile() }
+    }
+
+    /// Reads the SGI frame's `GICR_ISPENDR_E<i>`, the pending state of extended PPIs `32*i` to
+    /// `32*i + 31`.
+    pub fn gicr_pending_e(&self, i: usize) -> Result<u32, ExtendedRangeError> {
+        self.check_extended_ppi_supported()?;
+        Ok(unsafe { addr_of!((*self.sgi).ispendr_e[i]).read_volatile() })
+    }
+
+    /// Reads `GICD_ISACTIVER<i>`, the active state of SPIs `32*i` to `32*i + 31`.
+    pub fn gicd_active(&self, i: usize) -> u32 {
+        unsafe { addr_of!((*self.gicd).isactiver[i]).read_volatile() }
+    }
+
+    /// Reads `GICD_ISACTIVER_E<i>`, the active state of extended SPIs `32*i` to `32*i + 31`.
+    pub fn gicd_active_e(&self, i: usize) -> Result<u32, ExtendedRangeError> {
+        self.check_extended_spi_supported()?;
+        Ok(unsafe { addr_of!((*self.gicd).isactive_e[i]).read_volatile() })
+    }
+
+    /// Reads the SGI frame's `GICR_ISACTIVER0`, the active state of SGIs and PPIs 0-31.
+    pub fn gicr_active(&self) -> u32 {
+        unsafe { addr_of!((*self.sgi).isactiver0).read_volatile() }
+    }
+
+    /// Reads the SGI frame's `GICR_ISACTIVER_E<i>`, the active state of extended PPIs `32*i` to
+    /// `32*i + 31`.
+    pub fn gicr_active_e(&self, i: usize) -> Result<u32, ExtendedRangeError> {
+        self.check_extended_ppi_supported()?;
+        Ok(unsafe { addr_of!((*self.sgi).isactive_e[i]).read_volatile() })
     }
 }
@@ -22,13 +22,16 @@ mod logger;
 mod pl011;
 // ANCHOR_END: top
 mod pl031;
+mod sgi_channel;
 
-use crate::gicv3::{irq_enable, GicV3, Trigger};
+use crate::exceptions::WOKEN;
+use crate::gicv3::{irq_enable, Affinity, GicV3, IntId, Trigger};
 use crate::pl031::Rtc;
+use crate::sgi_channel::CHANNEL;
 use chrono::{TimeZone, Utc};
 // ANCHOR: imports
 use crate::pl011::Uart;
-use core::{hint::spin_loop, panic::PanicInfo};
+use core::{hint::spin_loop, panic::PanicInfo, sync::atomic::Ordering};
 use log::{error, info, LevelFilter};
 use psci::system_off;
 
@@ -43,7 +46,7 @@ const PL011_BASE_ADDRESS: *mut u32 = 0x900_0000 as _;
 /// Base address of the PL031 RTC.
 const PL031_BASE_ADDRESS: *mut u32 = 0x901_0000 as _;
 // SPI interrupt 2, level triggered
-const PL031_IRQ: u32 = 2;
+const PL031_IRQ: IntId = IntId::spi(2);
 
 // ANCHOR: main
 #[no_mangle]
@@ -56,22 +59,27 @@ extern "C" fn main(x0: u64, x1: u64, x2: u64, x3: u64) {
     info!("main({:#x}, {:#x}, {:#x}, {:#x})", x0, x1, x2, x3);
     // ANCHOR_END: main
 
-    let mut gic = unsafe { GicV3::new(GICD_BASE_ADDRESS, GICR_BASE_ADDRESS) };
+    // Safe because `GICD_BASE_ADDRESS` and `GICR_BASE_ADDRESS` are the base addresses of a
+    // GICv3 distributor and redistributor region respectively, and nothing else accesses them.
+    let mut gic = unsafe { GicV3::new_for_current_cpu(GICD_BASE_ADDRESS, GICR_BASE_ADDRESS) }
+        .expect("no redistributor frame found for the current CPU");
     gic.setup();
+    exceptions::set_wakeup_source(PL031_BASE_ADDRESS, PL031_IRQ);
 
-    // Test sending an SGI.
-    let sgi_intid = 3;
+    // Test sending a message over the SGI channel. This still targets our own affinity, as
+    // there's only one core running here, but goes through SgiChannel's doorbell/drain handshake
+    // rather than a raw `send_sgi` call.
     GicV3::set_priority_mask(0xff);
-    gic.set_interrupt_priority(sgi_intid.into(), 0x80);
+    gic.set_interrupt_priority(CHANNEL.doorbell(), 0x80).unwrap();
     irq_enable();
     gic.enable_all_interrupts(true);
     assert_eq!(gic.gicd_pending(0), 0);
     assert_eq!(gic.gicr_pending(), 0);
     assert_eq!(gic.gicd_active(0), 0);
     assert_eq!(gic.gicr_active(), 0);
-    info!("Sending SGI");
-    GicV3::send_sgi(sgi_intid, false, 0, 0, 0, 1);
-    info!("Sent SGI");
+    info!("Sending message over SGI channel");
+    CHANNEL.send(42, Affinity::current());
+    info!("Sent message over SGI channel");
     assert_eq!(gic.gicd_pending(0), 0);
     assert_eq!(gic.gicr_pending(), 0);
     assert_eq!(gic.gicd_active(0), 0);
@@ -85,8 +93,8 @@ extern "C" fn main(x0: u64, x1: u64, x2: u64, x3: u64) {
     info!("RTC: {time}");
 
     GicV3::set_priority_mask(0xff);
-    gic.set_interrupt_priority(PL031_IRQ, 0x80);
-    gic.set_trigger(PL031_IRQ, Trigger::Level);
+    gic.set_interrupt_priority(PL031_IRQ, 0x80).unwrap();
+    gic.set_trigger(PL031_IRQ, Trigger::Level).unwrap();
     irq_enable();
     gic.enable_all_interrupts(true);
 
@@ -97,7 +105,7 @@ extern "C" fn main(x0: u64, x1: u64, x2: u64, x3: u64) {
         "Waiting for {}",
         Utc.timestamp_opt(target.into(), 0).unwrap()
     );
-    while !rtc.matched() {
+    while !WOKEN.load(Ordering::Acquire) {
         spin_loop();
     }
     info!("Finished waiting");